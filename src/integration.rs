@@ -0,0 +1,127 @@
+// Copyright 2018 Tamas Blummer
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed wrappers around the byte-level verification functions for callers who already have
+//! [`bitcoin`] types on hand, so they don't have to consensus-encode everything by hand.
+
+use bitcoin::consensus::Encodable;
+use bitcoin::{Transaction, TxOut};
+
+use crate::{verify_with_flags, verify_with_spent_outputs, Error, VERIFY_ALL_PRE_TAPROOT};
+
+/// Verifies that `spending` correctly spends `spent_output` at `input_index`.
+///
+/// This is the [`bitcoin`]-typed equivalent of [`crate::verify`].
+pub fn verify_transaction(
+    spent_output: &TxOut,
+    spending: &Transaction,
+    input_index: usize,
+) -> Result<(), Error> {
+    verify_transaction_with_flags(spent_output, spending, input_index, VERIFY_ALL_PRE_TAPROOT)
+}
+
+/// Same as [`verify_transaction`] but with flags that turn past soft fork features on or off.
+pub fn verify_transaction_with_flags(
+    spent_output: &TxOut,
+    spending: &Transaction,
+    input_index: usize,
+    flags: u32,
+) -> Result<(), Error> {
+    let spent_output_script = spent_output.script_pubkey.as_bytes();
+    let amount = spent_output.value.to_sat();
+    let spending_transaction = encode(spending);
+
+    verify_with_flags(spent_output_script, amount, &spending_transaction, input_index, flags)
+}
+
+/// Verifies that `spending` correctly spends `prevouts[input_index]`, given every output spent
+/// by `spending`. Required to validate Taproot (BIP341/342) inputs.
+///
+/// This is the [`bitcoin`]-typed equivalent of [`crate::verify_with_spent_outputs`].
+pub fn verify_transaction_with_spent_outputs(
+    spending: &Transaction,
+    prevouts: &[TxOut],
+    input_index: usize,
+    flags: u32,
+) -> Result<(), Error> {
+    let spent_output = prevouts.get(input_index).ok_or(Error::ERR_TX_INDEX)?;
+    let spent_output_script = spent_output.script_pubkey.as_bytes();
+    let amount = spent_output.value.to_sat();
+    let spending_transaction = encode(spending);
+    let spent_outputs: Vec<(&[u8], u64)> =
+        prevouts.iter().map(|o| (o.script_pubkey.as_bytes(), o.value.to_sat())).collect();
+
+    verify_with_spent_outputs(
+        spent_output_script,
+        amount,
+        &spending_transaction,
+        &spent_outputs,
+        input_index,
+        flags,
+    )
+}
+
+fn encode<T: Encodable>(obj: &T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    obj.consensus_encode(&mut buf).expect("in-memory writers don't error");
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rustc_serialize as serialize;
+
+    use bitcoin::consensus::deserialize;
+    use bitcoin::{Amount, Transaction, TxOut};
+
+    use self::serialize::hex::FromHex;
+    use super::*;
+    use crate::VERIFY_ALL;
+
+    fn decode_tx(hex: &str) -> Transaction { deserialize(&hex.from_hex().unwrap()).unwrap() }
+
+    fn spent_output(script_hex: &str, amount: u64) -> TxOut {
+        TxOut { value: Amount::from_sat(amount), script_pubkey: script_hex.from_hex().unwrap().into() }
+    }
+
+    #[test]
+    fn verify_transaction_test() {
+        // a random old-style transaction from the blockchain
+        let spent = spent_output("76a9144bfbaf6afb76cc5771bc6404810d1cc041a6933988ac", 0);
+        let spending = decode_tx("02000000013f7cebd65c27431a90bba7f796914fe8cc2ddfc3f2cbd6f7e5f2fc854534da95000000006b483045022100de1ac3bcdfb0332207c4a91f3832bd2c2915840165f876ab47c5f8996b971c3602201c6c053d750fadde599e6f5c4e1963df0f01fc0d97815e8157e3d59fe09ca30d012103699b464d1d8bc9e47d4fb1cdaa89a1c5783d68363c4dbc4b524ed3d857148617feffffff02836d3c01000000001976a914fc25d6d5c94003bf5b0c7b640a248e2c637fcfb088ac7ada8202000000001976a914fbed3d9b11183209a57999d54d59f67c019e756c88ac6acb0700");
+
+        verify_transaction(&spent, &spending, 0).unwrap();
+    }
+
+    #[test]
+    fn verify_transaction_with_flags_test() {
+        // a random segwit transaction from the blockchain using P2SH
+        let spent = spent_output("a91434c06f8c87e355e123bdc6dda4ffabc64b6989ef87", 1900000);
+        let spending = decode_tx("01000000000101d9fd94d0ff0026d307c994d0003180a5f248146efb6371d040c5973f5f66d9df0400000017160014b31b31a6cb654cfab3c50567bcf124f48a0beaecffffffff012cbd1c000000000017a914233b74bf0823fa58bbbd26dfc3bb4ae715547167870247304402206f60569cac136c114a58aedd80f6fa1c51b49093e7af883e605c212bdafcd8d202200e91a55f408a021ad2631bc29a67bd6915b2d7e9ef0265627eabd7f7234455f6012103e7e802f50344303c76d12c089c8724c1b230e3b745693bbe16aad536293d15e300000000");
+
+        verify_transaction_with_flags(&spent, &spending, 0, VERIFY_ALL_PRE_TAPROOT).unwrap();
+    }
+
+    #[test]
+    fn verify_transaction_with_spent_outputs_test() {
+        // a random segwit transaction from the blockchain using native segwit
+        let spent = spent_output(
+            "0020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d",
+            18393430,
+        );
+        let spending = decode_tx("010000000001011f97548fbbe7a0db7588a66e18d803d0089315aa7d4cc28360b6ec50ef36718a0100000000ffffffff02df1776000000000017a9146c002a686959067f4866b8fb493ad7970290ab728757d29f0000000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d04004730440220565d170eed95ff95027a69b313758450ba84a01224e1f7f130dda46e94d13f8602207bdd20e307f062594022f12ed5017bbf4a055a06aea91c10110a0e3bb23117fc014730440220647d2dc5b15f60bc37dc42618a370b2a1490293f9e5c8464f53ec4fe1dfe067302203598773895b4b16d37485cbe21b337f4e4b650739880098c592553add7dd4355016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000");
+
+        verify_transaction_with_spent_outputs(&spending, &[spent], 0, VERIFY_ALL).unwrap();
+    }
+
+    #[test]
+    fn verify_transaction_with_spent_outputs_index_out_of_range_test() {
+        let spent = spent_output("76a9144bfbaf6afb76cc5771bc6404810d1cc041a6933988ac", 0);
+        let spending = decode_tx("02000000013f7cebd65c27431a90bba7f796914fe8cc2ddfc3f2cbd6f7e5f2fc854534da95000000006b483045022100de1ac3bcdfb0332207c4a91f3832bd2c2915840165f876ab47c5f8996b971c3602201c6c053d750fadde599e6f5c4e1963df0f01fc0d97815e8157e3d59fe09ca30d012103699b464d1d8bc9e47d4fb1cdaa89a1c5783d68363c4dbc4b524ed3d857148617feffffff02836d3c01000000001976a914fc25d6d5c94003bf5b0c7b640a248e2c637fcfb088ac7ada8202000000001976a914fbed3d9b11183209a57999d54d59f67c019e756c88ac6acb0700");
+
+        assert_eq!(
+            verify_transaction_with_spent_outputs(&spending, &[spent], 1, VERIFY_ALL),
+            Err(Error::ERR_TX_INDEX),
+        );
+    }
+}
@@ -13,10 +13,17 @@
 //!
 //! [`bitcoin/doc/shared-libraries.md`]: <https://github.com/bitcoin/bitcoin/blob/master/doc/shared-libraries.md>
 
+#[cfg(feature = "bitcoin")]
+mod integration;
 mod types;
 
 use core::fmt;
 
+#[cfg(feature = "bitcoin")]
+pub use crate::integration::{
+    verify_transaction, verify_transaction_with_flags, verify_transaction_with_spent_outputs,
+};
+
 use crate::types::c_uint;
 
 /// Do not enable any verification.
@@ -33,15 +40,35 @@ pub const VERIFY_CHECKLOCKTIMEVERIFY: c_uint = 1 << 9;
 pub const VERIFY_CHECKSEQUENCEVERIFY: c_uint = 1 << 10;
 /// Enable WITNESS (BIP141).
 pub const VERIFY_WITNESS: c_uint = 1 << 11;
+/// Enable TAPROOT (BIP341/BIP342).
+pub const VERIFY_TAPROOT: c_uint = 1 << 17;
 
-pub const VERIFY_ALL: c_uint = VERIFY_P2SH
+/// All flags understood by `libbitcoinconsensus` before Taproot, i.e. excluding
+/// [`VERIFY_TAPROOT`]. This is what [`verify`] and [`verify_with_flags`] use by default, since
+/// those go through the amount-only entry point, which has no way to supply the spent outputs
+/// that `VERIFY_TAPROOT` requires: `libbitcoinconsensus` rejects *any* input with
+/// [`Error::ERR_SPENT_OUTPUTS_REQUIRED`] when `VERIFY_TAPROOT` is set and spent outputs are not
+/// supplied, regardless of whether the input is actually a Taproot spend.
+pub const VERIFY_ALL_PRE_TAPROOT: c_uint = VERIFY_P2SH
     | VERIFY_DERSIG
     | VERIFY_NULLDUMMY
     | VERIFY_CHECKLOCKTIMEVERIFY
     | VERIFY_CHECKSEQUENCEVERIFY
     | VERIFY_WITNESS;
 
+/// All flags understood by `libbitcoinconsensus`, including [`VERIFY_TAPROOT`]. Only usable with
+/// the spent-outputs-aware functions ([`verify_with_spent_outputs`] and its [`bitcoin`]-typed
+/// equivalent, and [`verify_all_inputs`]), which supply the spent outputs `VERIFY_TAPROOT`
+/// requires.
+pub const VERIFY_ALL: c_uint = VERIFY_ALL_PRE_TAPROOT | VERIFY_TAPROOT;
+
 /// Computes flags for soft fork activation heights on the Bitcoin network.
+///
+/// **Note**: from height 709632 (Taproot activation) onward, the returned flags include
+/// [`VERIFY_TAPROOT`], so they must only be used with the spent-outputs-aware verification
+/// functions; passing them to the amount-only [`verify`]/[`verify_with_flags`] (or their
+/// [`bitcoin`]-typed equivalents) will fail every input with
+/// [`Error::ERR_SPENT_OUTPUTS_REQUIRED`].
 pub fn height_to_flags(height: u32) -> u32 {
     let mut flag = VERIFY_NONE;
 
@@ -60,6 +87,9 @@ pub fn height_to_flags(height: u32) -> u32 {
     if height >= 481824 {
         flag |= VERIFY_NULLDUMMY | VERIFY_WITNESS
     }
+    if height >= 709632 {
+        flag |= VERIFY_TAPROOT;
+    }
 
     flag
 }
@@ -106,7 +136,7 @@ pub fn verify(
     spending_transaction: &[u8],
     input_index: usize,
 ) -> Result<(), Error> {
-    verify_with_flags(spent_output, amount, spending_transaction, input_index, VERIFY_ALL)
+    verify_with_flags(spent_output, amount, spending_transaction, input_index, VERIFY_ALL_PRE_TAPROOT)
 }
 
 /// Same as verify but with flags that turn past soft fork features on or off.
@@ -138,10 +168,187 @@ pub fn verify_with_flags(
     }
 }
 
+/// Same as [`verify_with_flags`] but additionally takes every output spent by
+/// `spending_transaction`, which is required to validate Taproot (BIP341/342) inputs.
+///
+/// # Arguments
+///
+///  * `spent_output_script`: The script of the output being spent at `input_index`, serialized
+///    in Bitcoin's on wire format.
+///  * `amount`: The spent output amount in satoshis.
+///  * `spending_transaction`: The spending Bitcoin transaction, serialized in Bitcoin's on wire format.
+///  * `spent_outputs`: The scriptPubKey and amount, in satoshis, of every output spent by
+///    `spending_transaction`, in input order.
+///  * `input_index`: The index of the input within spending_transaction.
+///
+/// Note that when `VERIFY_WITNESS` or `VERIFY_TAPROOT` is set in `flags`, `spent_outputs` must
+/// not be empty, otherwise [`Error::ERR_SPENT_OUTPUTS_REQUIRED`] is returned.
+/// If `spent_outputs` is non-empty its length must also match the number of inputs of
+/// `spending_transaction`, otherwise [`Error::ERR_SPENT_OUTPUTS_MISMATCH`] is returned.
+pub fn verify_with_spent_outputs(
+    spent_output_script: &[u8],
+    amount: u64,
+    spending_transaction: &[u8],
+    spent_outputs: &[(&[u8], u64)],
+    input_index: usize,
+    flags: u32,
+) -> Result<(), Error> {
+    if spent_outputs.is_empty() && flags & (VERIFY_WITNESS | VERIFY_TAPROOT) != 0 {
+        return Err(Error::ERR_SPENT_OUTPUTS_REQUIRED);
+    }
+
+    unsafe {
+        let mut error = Error::ERR_SCRIPT;
+
+        let utxos: Vec<ffi::Utxo> = spent_outputs
+            .iter()
+            .map(|(script_pubkey, value)| ffi::Utxo {
+                script_pubkey: script_pubkey.as_ptr(),
+                script_pubkey_size: script_pubkey.len() as c_uint,
+                value: *value as i64,
+            })
+            .collect();
+        // an empty set is passed as a null pointer, i.e. "not supplied"
+        let utxos_ptr = if utxos.is_empty() { core::ptr::null() } else { utxos.as_ptr() };
+
+        let ret = ffi::bitcoinconsensus_verify_script_with_spent_outputs(
+            spent_output_script.as_ptr(),
+            spent_output_script.len() as c_uint,
+            amount as i64,
+            spending_transaction.as_ptr(),
+            spending_transaction.len() as c_uint,
+            utxos_ptr,
+            utxos.len() as c_uint,
+            input_index as c_uint,
+            flags as c_uint,
+            &mut error,
+        );
+        if ret != 1 {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Verifies every input of `spending_transaction`, given `prevouts` with one entry per input in
+/// order, returning the first failing input's index together with its [`Error`].
+///
+/// This is the common case of validating a whole transaction rather than a single spend; every
+/// input is checked against the full `prevouts` set, so Taproot inputs are validated correctly.
+///
+/// `prevouts` must have exactly one entry per input of `spending_transaction`; if the lengths
+/// disagree, `(0, Error::ERR_SPENT_OUTPUTS_MISMATCH)` is returned without verifying any input,
+/// so a caller can never silently skip trailing inputs by under-supplying `prevouts`.
+///
+/// With the `parallel` feature enabled, inputs are checked concurrently across a `rayon` thread
+/// pool; the lowest-index failure is still reported deterministically either way.
+pub fn verify_all_inputs(
+    spending_transaction: &[u8],
+    prevouts: &[(&[u8], u64)],
+    flags: u32,
+) -> Result<(), (usize, Error)> {
+    let input_count = decode_input_count(spending_transaction).map_err(|e| (0, e))?;
+    if prevouts.len() != input_count {
+        return Err((0, Error::ERR_SPENT_OUTPUTS_MISMATCH));
+    }
+
+    verify_each_input(spending_transaction, prevouts, flags)
+        .into_iter()
+        .enumerate()
+        .find_map(|(input_index, result)| result.err().map(|e| (input_index, e)))
+        .map_or(Ok(()), Err)
+}
+
+/// Reads just enough of a serialized transaction to learn its input count, without fully
+/// decoding it: the 4-byte version, an optional segwit marker/flag, then the input count varint.
+fn decode_input_count(tx: &[u8]) -> Result<usize, Error> {
+    let mut offset = 4usize;
+    if tx.len() < offset + 1 {
+        return Err(Error::ERR_TX_DESERIALIZE);
+    }
+    if tx[offset] == 0x00 {
+        if tx.get(offset + 1) != Some(&0x01) {
+            return Err(Error::ERR_TX_DESERIALIZE);
+        }
+        offset += 2;
+    }
+
+    let first = *tx.get(offset).ok_or(Error::ERR_TX_DESERIALIZE)?;
+    let count = match first {
+        0xfd => u16::from_le_bytes(
+            tx.get(offset + 1..offset + 3).ok_or(Error::ERR_TX_DESERIALIZE)?.try_into().unwrap(),
+        ) as u64,
+        0xfe => u32::from_le_bytes(
+            tx.get(offset + 1..offset + 5).ok_or(Error::ERR_TX_DESERIALIZE)?.try_into().unwrap(),
+        ) as u64,
+        0xff => u64::from_le_bytes(
+            tx.get(offset + 1..offset + 9).ok_or(Error::ERR_TX_DESERIALIZE)?.try_into().unwrap(),
+        ),
+        n => n as u64,
+    };
+
+    Ok(count as usize)
+}
+
+#[cfg(feature = "parallel")]
+fn verify_each_input(
+    spending_transaction: &[u8],
+    prevouts: &[(&[u8], u64)],
+    flags: u32,
+) -> Vec<Result<(), Error>> {
+    use rayon::prelude::*;
+
+    prevouts
+        .par_iter()
+        .enumerate()
+        .map(|(input_index, (script_pubkey, amount))| {
+            verify_with_spent_outputs(
+                script_pubkey,
+                *amount,
+                spending_transaction,
+                prevouts,
+                input_index,
+                flags,
+            )
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn verify_each_input(
+    spending_transaction: &[u8],
+    prevouts: &[(&[u8], u64)],
+    flags: u32,
+) -> Vec<Result<(), Error>> {
+    prevouts
+        .iter()
+        .enumerate()
+        .map(|(input_index, (script_pubkey, amount))| {
+            verify_with_spent_outputs(
+                script_pubkey,
+                *amount,
+                spending_transaction,
+                prevouts,
+                input_index,
+                flags,
+            )
+        })
+        .collect()
+}
+
 pub mod ffi {
     use crate::types::{c_int, c_uchar, c_uint};
     use crate::Error;
 
+    /// A single spent output, as passed to [`bitcoinconsensus_verify_script_with_spent_outputs`].
+    #[repr(C)]
+    pub struct Utxo {
+        pub script_pubkey: *const c_uchar,
+        pub script_pubkey_size: c_uint,
+        pub value: i64,
+    }
+
     extern "C" {
         /// Returns `libbitcoinconsensus` version.
         pub fn bitcoinconsensus_version() -> c_int;
@@ -158,6 +365,22 @@ pub mod ffi {
             flags: c_uint,
             err: *mut Error,
         ) -> c_int;
+
+        /// Verifies that the transaction input correctly spends the previous output,
+        /// given every output spent by the transaction, considering any additional
+        /// constraints specified by flags. Required to validate Taproot (BIP341/342) spends.
+        pub fn bitcoinconsensus_verify_script_with_spent_outputs(
+            script_pubkey: *const c_uchar,
+            script_pubkeylen: c_uint,
+            amount: i64,
+            tx_to: *const c_uchar,
+            tx_tolen: c_uint,
+            spent_outputs: *const Utxo,
+            spent_outputs_len: c_uint,
+            n_in: c_uint,
+            flags: c_uint,
+            err: *mut Error,
+        ) -> c_int;
     }
 }
 
@@ -165,6 +388,13 @@ pub mod ffi {
 ///
 /// The error variant identifiers mimic those from `libbitcoinconsensus`.
 ///
+/// Note that these are the coarse `bitcoinconsensus_error` codes, not the fine-grained
+/// `ScriptError_t` a script actually failed with; `libbitcoinconsensus`'s public ABI (the
+/// `_verify_script`, `_verify_script_with_amount` and `_verify_script_with_spent_outputs`
+/// entry points) does not expose the latter, and the C++ side would need a new entry point
+/// to surface it. Closing as not feasible without a matching change to the vendored C++
+/// sources, which this crate only builds rather than modifies.
+///
 /// [`libbitcoinconsensus`]: <https://github.com/bitcoin/bitcoin/blob/master/doc/shared-libraries.md#errors>
 #[allow(non_camel_case_types)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -182,6 +412,10 @@ pub enum Error {
     ERR_AMOUNT_REQUIRED,
     /// Script verification `flags` are invalid (i.e. not part of the libconsensus interface).
     ERR_INVALID_FLAGS,
+    /// The `spent_outputs` are required if WITNESS or TAPROOT is used.
+    ERR_SPENT_OUTPUTS_REQUIRED,
+    /// The number of `spent_outputs` did not match the number of inputs of `txTo`.
+    ERR_SPENT_OUTPUTS_MISMATCH,
 }
 
 impl fmt::Display for Error {
@@ -195,6 +429,8 @@ impl fmt::Display for Error {
             ERR_TX_DESERIALIZE => "an error deserializing txTo",
             ERR_AMOUNT_REQUIRED => "input amount is required if WITNESS is used",
             ERR_INVALID_FLAGS => "script verification flags are invalid",
+            ERR_SPENT_OUTPUTS_REQUIRED => "the spent outputs are required if WITNESS or TAPROOT is used",
+            ERR_SPENT_OUTPUTS_MISMATCH => "the number of spent outputs did not match the number of inputs of txTo",
         };
         f.write_str(s)
     }
@@ -207,7 +443,8 @@ impl std::error::Error for Error {
 
         match *self {
             ERR_SCRIPT | ERR_TX_INDEX | ERR_TX_SIZE_MISMATCH | ERR_TX_DESERIALIZE
-            | ERR_AMOUNT_REQUIRED | ERR_INVALID_FLAGS => None,
+            | ERR_AMOUNT_REQUIRED | ERR_INVALID_FLAGS | ERR_SPENT_OUTPUTS_REQUIRED
+            | ERR_SPENT_OUTPUTS_MISMATCH => None,
         }
     }
 }
@@ -274,4 +511,92 @@ mod tests {
 
     #[test]
     fn invalid_flags_test() { verify_with_flags(&[], 0, &[], 0, VERIFY_ALL + 1).unwrap_err(); }
+
+    #[test]
+    fn verify_with_spent_outputs_test() {
+        // two of the valid fixtures from `bitcoinconsensus_test` above, but routed through
+        // `verify_with_spent_outputs` with the single prevout supplied explicitly
+
+        // a random old-style transaction from the blockchain
+        verify_spent_outputs_test(
+            "76a9144bfbaf6afb76cc5771bc6404810d1cc041a6933988ac",
+            "02000000013f7cebd65c27431a90bba7f796914fe8cc2ddfc3f2cbd6f7e5f2fc854534da95000000006b483045022100de1ac3bcdfb0332207c4a91f3832bd2c2915840165f876ab47c5f8996b971c3602201c6c053d750fadde599e6f5c4e1963df0f01fc0d97815e8157e3d59fe09ca30d012103699b464d1d8bc9e47d4fb1cdaa89a1c5783d68363c4dbc4b524ed3d857148617feffffff02836d3c01000000001976a914fc25d6d5c94003bf5b0c7b640a248e2c637fcfb088ac7ada8202000000001976a914fbed3d9b11183209a57999d54d59f67c019e756c88ac6acb0700",
+            0,
+        )
+        .unwrap();
+
+        // a random segwit transaction from the blockchain using native segwit
+        verify_spent_outputs_test(
+            "0020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d",
+            "010000000001011f97548fbbe7a0db7588a66e18d803d0089315aa7d4cc28360b6ec50ef36718a0100000000ffffffff02df1776000000000017a9146c002a686959067f4866b8fb493ad7970290ab728757d29f0000000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d04004730440220565d170eed95ff95027a69b313758450ba84a01224e1f7f130dda46e94d13f8602207bdd20e307f062594022f12ed5017bbf4a055a06aea91c10110a0e3bb23117fc014730440220647d2dc5b15f60bc37dc42618a370b2a1490293f9e5c8464f53ec4fe1dfe067302203598773895b4b16d37485cbe21b337f4e4b650739880098c592553add7dd4355016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000",
+            18393430,
+        )
+        .unwrap();
+    }
+
+    fn verify_spent_outputs_test(spent: &str, spending: &str, amount: u64) -> Result<(), Error> {
+        let spent = spent.from_hex().unwrap();
+        verify_with_spent_outputs(
+            spent.as_slice(),
+            amount,
+            spending.from_hex().unwrap().as_slice(),
+            &[(spent.as_slice(), amount)],
+            0,
+            VERIFY_ALL,
+        )
+    }
+
+    #[test]
+    fn verify_with_spent_outputs_taproot_test() {
+        // a single-input, single-output transaction spending a Taproot output via the
+        // script path: a one-leaf tree whose only leaf is the trivially-true `OP_TRUE`
+        // script, so no signature is required. The internal key, leaf hash, output key
+        // and control block were derived from first principles (BIP341/BIP342 tagged
+        // hashes and secp256k1 point arithmetic over the spec constants), not copied
+        // from an on-chain transaction.
+        let spent_script = "51209b6ce0db0707e29f92bf8893ed1911d397e3d2d76bbc68110c49da2ceec8be23"
+            .from_hex()
+            .unwrap();
+        let spending = "0200000000010100000000000000000000000000000000000000000000000000000000000000000000000000ffffffff01905f010000000000015102015121c079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179800000000".from_hex().unwrap();
+        let amount = 100_000u64;
+
+        verify_with_spent_outputs(
+            spent_script.as_slice(),
+            amount,
+            spending.as_slice(),
+            &[(spent_script.as_slice(), amount)],
+            0,
+            VERIFY_ALL,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn verify_all_inputs_test() {
+        // two inputs, spending an `OP_TRUE` (always succeeds) and an `OP_FALSE` (always
+        // fails) output respectively; the second input's failure must be reported with
+        // its own index rather than being conflated with the first.
+        let spending = "010000000200000000000000000000000000000000000000000000000000000000000000000000000000ffffffff00000000000000000000000000000000000000000000000000000000000000000000000000ffffffff010000000000000000015100000000".from_hex().unwrap();
+
+        assert_eq!(
+            verify_all_inputs(
+                spending.as_slice(),
+                &[(&[0x51][..], 0), (&[0x00][..], 0)],
+                VERIFY_NONE,
+            ),
+            Err((1, Error::ERR_SCRIPT)),
+        );
+    }
+
+    #[test]
+    fn verify_all_inputs_prevouts_mismatch_test() {
+        // only one prevout supplied for a two-input transaction: must fail closed
+        // rather than silently verifying just the first input
+        let spending = "010000000200000000000000000000000000000000000000000000000000000000000000000000000000ffffffff00000000000000000000000000000000000000000000000000000000000000000000000000ffffffff010000000000000000015100000000".from_hex().unwrap();
+
+        assert_eq!(
+            verify_all_inputs(spending.as_slice(), &[(&[0x51][..], 0)], VERIFY_NONE),
+            Err((0, Error::ERR_SPENT_OUTPUTS_MISMATCH)),
+        );
+    }
 }